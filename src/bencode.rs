@@ -0,0 +1,557 @@
+//! Bencode encoding as used by the BitTorrent metainfo format.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+
+/// A bencode parse failure, tagged with the byte offset where it occurred.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BencodeError {
+    /// The input (or reader) ended before a value could be completed.
+    InputTooShort { offset: usize },
+    /// The byte at `offset` doesn't start any known bencode value.
+    UnknownType { byte: u8, offset: usize },
+    /// A type byte (`i`, `l`, `d`) didn't match what the streaming decoder
+    /// expected at `offset`.
+    ExpectedByte {
+        expected: u8,
+        found: Option<u8>,
+        offset: usize,
+    },
+    /// An `i...e` integer, or a byte string's `<len>:` prefix, was malformed.
+    InvalidInteger { offset: usize },
+    /// A dict's keys were not in ascending lexicographic order.
+    UnsortedDictKey { offset: usize },
+    /// The top-level value decoded successfully but bytes remain after it.
+    TrailingData { offset: usize },
+}
+
+impl fmt::Display for BencodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InputTooShort { offset } => {
+                write!(f, "bencode error: input too short at offset {}", offset)
+            }
+            Self::UnknownType { byte, offset } => write!(
+                f,
+                "bencode error: unknown value type byte {:#04x} at offset {}",
+                byte, offset
+            ),
+            Self::ExpectedByte {
+                expected,
+                found,
+                offset,
+            } => write!(
+                f,
+                "bencode error: expected byte {:?} at offset {}, found {:?}",
+                *expected as char,
+                offset,
+                found.map(|b| b as char)
+            ),
+            Self::InvalidInteger { offset } => {
+                write!(f, "bencode error: invalid integer at offset {}", offset)
+            }
+            Self::UnsortedDictKey { offset } => write!(
+                f,
+                "bencode error: dict key at offset {} is out of order",
+                offset
+            ),
+            Self::TrailingData { offset } => {
+                write!(f, "bencode error: trailing data at offset {}", offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BencodeError {}
+
+/// A fully-decoded bencode value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Bytes(Vec<u8>),
+    List(Vec<Value>),
+    Dict(BTreeMap<Vec<u8>, Value>),
+}
+
+impl Value {
+    /// Decode a complete bencoded buffer, erroring on anything left over.
+    pub fn decode(input: &[u8]) -> Result<Value, BencodeError> {
+        let (value, consumed) = decode_value(input, 0)?;
+        if consumed != input.len() {
+            return Err(BencodeError::TrailingData { offset: consumed });
+        }
+        Ok(value)
+    }
+
+    /// Decode a single value from a reader, pulling bytes on demand rather
+    /// than requiring the whole input up front.
+    pub fn decode_from(reader: &mut dyn io::Read) -> Result<Value, BencodeError> {
+        let mut source = Source::new(reader);
+        let value = decode_value_streaming(&mut source)?;
+        match peek_byte(&mut source)? {
+            Some(_) => Err(BencodeError::TrailingData {
+                offset: source.offset(),
+            }),
+            None => Ok(value),
+        }
+    }
+
+    /// Encode this value in canonical bencode form.
+    pub fn encode(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        match self {
+            Value::Int(n) => write!(out, "i{}e", n),
+            Value::Bytes(bytes) => {
+                write!(out, "{}:", bytes.len())?;
+                out.write_all(bytes)
+            }
+            Value::List(items) => {
+                out.write_all(b"l")?;
+                for item in items {
+                    item.encode(out)?;
+                }
+                out.write_all(b"e")
+            }
+            Value::Dict(entries) => {
+                out.write_all(b"d")?;
+                // `BTreeMap` already iterates keys in ascending byte order,
+                // which is exactly what canonical bencode requires.
+                for (key, value) in entries {
+                    write!(out, "{}:", key.len())?;
+                    out.write_all(key)?;
+                    value.encode(out)?;
+                }
+                out.write_all(b"e")
+            }
+        }
+    }
+
+    /// Convenience wrapper around [`Value::encode`] for callers that just want bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode(&mut out)
+            .expect("encoding into a Vec<u8> is infallible");
+        out
+    }
+}
+
+fn decode_value(input: &[u8], pos: usize) -> Result<(Value, usize), BencodeError> {
+    match input.get(pos) {
+        None => Err(BencodeError::InputTooShort { offset: pos }),
+        Some(b'i') => decode_int(input, pos),
+        Some(b'l') => decode_list(input, pos),
+        Some(b'd') => decode_dict(input, pos),
+        Some(b) if b.is_ascii_digit() => {
+            let (bytes, next) = decode_bytes(input, pos)?;
+            Ok((Value::Bytes(bytes), next))
+        }
+        Some(&byte) => Err(BencodeError::UnknownType { byte, offset: pos }),
+    }
+}
+
+fn decode_int(input: &[u8], pos: usize) -> Result<(Value, usize), BencodeError> {
+    let start = pos + 1;
+    let end = input[start..]
+        .iter()
+        .position(|&b| b == b'e')
+        .map(|i| start + i)
+        .ok_or(BencodeError::InputTooShort { offset: start })?;
+    let text = parse_integer_text(input, start, end)?;
+    let value = text
+        .parse::<i64>()
+        .map_err(|_| BencodeError::InvalidInteger { offset: start })?;
+    Ok((Value::Int(value), end + 1))
+}
+
+/// Validate and return the digits of an `i<digits>e` integer: no empty
+/// text, no bare `-`, no `-0`, and no leading zeros.
+fn parse_integer_text(input: &[u8], start: usize, end: usize) -> Result<&str, BencodeError> {
+    let text = std::str::from_utf8(&input[start..end])
+        .map_err(|_| BencodeError::InvalidInteger { offset: start })?;
+    if text.is_empty() || text == "-" {
+        return Err(BencodeError::InvalidInteger { offset: start });
+    }
+    let unsigned = text.strip_prefix('-').unwrap_or(text);
+    if text == "-0" || (unsigned.len() > 1 && unsigned.starts_with('0')) {
+        return Err(BencodeError::InvalidInteger { offset: start });
+    }
+    Ok(text)
+}
+
+fn decode_bytes(input: &[u8], pos: usize) -> Result<(Vec<u8>, usize), BencodeError> {
+    let colon = input[pos..]
+        .iter()
+        .position(|&b| b == b':')
+        .map(|i| pos + i)
+        .ok_or(BencodeError::InputTooShort { offset: pos })?;
+    let len_text = parse_unsigned_len_text(input, pos, colon)?;
+    let len = len_text
+        .parse::<usize>()
+        .map_err(|_| BencodeError::InvalidInteger { offset: pos })?;
+    let start = colon + 1;
+    let end = start
+        .checked_add(len)
+        .ok_or(BencodeError::InvalidInteger { offset: pos })?;
+    if end > input.len() {
+        return Err(BencodeError::InputTooShort { offset: start });
+    }
+    Ok((input[start..end].to_vec(), end))
+}
+
+fn parse_unsigned_len_text(input: &[u8], start: usize, end: usize) -> Result<&str, BencodeError> {
+    let text = std::str::from_utf8(&input[start..end])
+        .map_err(|_| BencodeError::InvalidInteger { offset: start })?;
+    if text.is_empty() || (text.len() > 1 && text.starts_with('0')) {
+        return Err(BencodeError::InvalidInteger { offset: start });
+    }
+    Ok(text)
+}
+
+fn decode_list(input: &[u8], pos: usize) -> Result<(Value, usize), BencodeError> {
+    let mut offset = pos + 1;
+    let mut items = Vec::new();
+    loop {
+        match input.get(offset) {
+            None => return Err(BencodeError::InputTooShort { offset }),
+            Some(b'e') => {
+                offset += 1;
+                break;
+            }
+            Some(_) => {
+                let (value, next) = decode_value(input, offset)?;
+                items.push(value);
+                offset = next;
+            }
+        }
+    }
+    Ok((Value::List(items), offset))
+}
+
+fn decode_dict(input: &[u8], pos: usize) -> Result<(Value, usize), BencodeError> {
+    let mut offset = pos + 1;
+    let mut entries = BTreeMap::new();
+    let mut last_key: Option<Vec<u8>> = None;
+    loop {
+        match input.get(offset) {
+            None => return Err(BencodeError::InputTooShort { offset }),
+            Some(b'e') => {
+                offset += 1;
+                break;
+            }
+            Some(_) => {
+                let key_offset = offset;
+                let (key, next) = decode_bytes(input, offset)?;
+                offset = next;
+                if let Some(last) = &last_key {
+                    if key <= *last {
+                        return Err(BencodeError::UnsortedDictKey { offset: key_offset });
+                    }
+                }
+                let (value, next) = decode_value(input, offset)?;
+                offset = next;
+                last_key = Some(key.clone());
+                entries.insert(key, value);
+            }
+        }
+    }
+    Ok((Value::Dict(entries), offset))
+}
+
+/// A one-byte lookahead buffer over an `io::Read`, so the streaming decoder
+/// can drive the same grammar as [`decode_value`] without first reading the
+/// whole input into memory. Tracks how many bytes have been consumed so
+/// streaming errors can report an offset just like the in-memory decoder.
+struct Source<'a> {
+    reader: &'a mut dyn io::Read,
+    peeked: Option<u8>,
+    pos: usize,
+}
+
+impl<'a> Source<'a> {
+    fn new(reader: &'a mut dyn io::Read) -> Self {
+        Source {
+            reader,
+            peeked: None,
+            pos: 0,
+        }
+    }
+
+    fn peek(&mut self) -> io::Result<Option<u8>> {
+        if self.peeked.is_none() {
+            let mut buf = [0u8; 1];
+            self.peeked = match self.reader.read(&mut buf)? {
+                0 => None,
+                _ => Some(buf[0]),
+            };
+        }
+        Ok(self.peeked)
+    }
+
+    fn skip(&mut self) {
+        if self.peeked.take().is_some() {
+            self.pos += 1;
+        }
+    }
+
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        let byte = self.peek()?;
+        self.skip();
+        Ok(byte)
+    }
+
+    fn offset(&self) -> usize {
+        self.pos
+    }
+}
+
+/// Pull the next byte, treating a genuine I/O error the same as running out
+/// of bytes: either way the decoder cannot continue past this offset.
+fn read_byte(source: &mut Source) -> Result<u8, BencodeError> {
+    let offset = source.offset();
+    match source.next_byte() {
+        Ok(Some(b)) => Ok(b),
+        Ok(None) | Err(_) => Err(BencodeError::InputTooShort { offset }),
+    }
+}
+
+fn peek_byte(source: &mut Source) -> Result<Option<u8>, BencodeError> {
+    source
+        .peek()
+        .map_err(|_| BencodeError::InputTooShort {
+            offset: source.offset(),
+        })
+}
+
+fn expect_byte(source: &mut Source, expected: u8) -> Result<(), BencodeError> {
+    let offset = source.offset();
+    let found = source.next_byte().unwrap_or(None);
+    if found == Some(expected) {
+        Ok(())
+    } else {
+        Err(BencodeError::ExpectedByte {
+            expected,
+            found,
+            offset,
+        })
+    }
+}
+
+fn decode_value_streaming(source: &mut Source) -> Result<Value, BencodeError> {
+    match peek_byte(source)? {
+        None => Err(BencodeError::InputTooShort {
+            offset: source.offset(),
+        }),
+        Some(b'i') => decode_int_streaming(source),
+        Some(b'l') => decode_list_streaming(source),
+        Some(b'd') => decode_dict_streaming(source),
+        Some(b) if b.is_ascii_digit() => decode_bytes_streaming(source).map(Value::Bytes),
+        Some(byte) => Err(BencodeError::UnknownType {
+            byte,
+            offset: source.offset(),
+        }),
+    }
+}
+
+fn decode_int_streaming(source: &mut Source) -> Result<Value, BencodeError> {
+    expect_byte(source, b'i')?;
+    let start = source.offset();
+    let mut digits = Vec::new();
+    loop {
+        match read_byte(source)? {
+            b'e' => break,
+            b => digits.push(b),
+        }
+    }
+    let text = std::str::from_utf8(&digits)
+        .map_err(|_| BencodeError::InvalidInteger { offset: start })?;
+    if text.is_empty() || text == "-" {
+        return Err(BencodeError::InvalidInteger { offset: start });
+    }
+    let unsigned = text.strip_prefix('-').unwrap_or(text);
+    if text == "-0" || (unsigned.len() > 1 && unsigned.starts_with('0')) {
+        return Err(BencodeError::InvalidInteger { offset: start });
+    }
+    let value = text
+        .parse::<i64>()
+        .map_err(|_| BencodeError::InvalidInteger { offset: start })?;
+    Ok(Value::Int(value))
+}
+
+fn decode_bytes_streaming(source: &mut Source) -> Result<Vec<u8>, BencodeError> {
+    let start = source.offset();
+    let mut len_digits = Vec::new();
+    loop {
+        match peek_byte(source)? {
+            Some(b':') => {
+                source.skip();
+                break;
+            }
+            Some(b) if b.is_ascii_digit() => {
+                len_digits.push(b);
+                source.skip();
+            }
+            Some(_) => return Err(BencodeError::InvalidInteger { offset: start }),
+            None => {
+                return Err(BencodeError::InputTooShort {
+                    offset: source.offset(),
+                })
+            }
+        }
+    }
+    let len_text = std::str::from_utf8(&len_digits)
+        .map_err(|_| BencodeError::InvalidInteger { offset: start })?;
+    if len_text.is_empty() || (len_text.len() > 1 && len_text.starts_with('0')) {
+        return Err(BencodeError::InvalidInteger { offset: start });
+    }
+    let len = len_text
+        .parse::<usize>()
+        .map_err(|_| BencodeError::InvalidInteger { offset: start })?;
+    let mut bytes = Vec::with_capacity(len);
+    for _ in 0..len {
+        bytes.push(read_byte(source)?);
+    }
+    Ok(bytes)
+}
+
+fn decode_list_streaming(source: &mut Source) -> Result<Value, BencodeError> {
+    expect_byte(source, b'l')?;
+    let mut items = Vec::new();
+    loop {
+        match peek_byte(source)? {
+            None => {
+                return Err(BencodeError::InputTooShort {
+                    offset: source.offset(),
+                })
+            }
+            Some(b'e') => {
+                source.skip();
+                break;
+            }
+            Some(_) => items.push(decode_value_streaming(source)?),
+        }
+    }
+    Ok(Value::List(items))
+}
+
+fn decode_dict_streaming(source: &mut Source) -> Result<Value, BencodeError> {
+    expect_byte(source, b'd')?;
+    let mut entries = BTreeMap::new();
+    let mut last_key: Option<Vec<u8>> = None;
+    loop {
+        match peek_byte(source)? {
+            None => {
+                return Err(BencodeError::InputTooShort {
+                    offset: source.offset(),
+                })
+            }
+            Some(b'e') => {
+                source.skip();
+                break;
+            }
+            Some(_) => {
+                let key_offset = source.offset();
+                let key = decode_bytes_streaming(source)?;
+                if let Some(last) = &last_key {
+                    if key <= *last {
+                        return Err(BencodeError::UnsortedDictKey { offset: key_offset });
+                    }
+                }
+                let value = decode_value_streaming(source)?;
+                last_key = Some(key.clone());
+                entries.insert(key, value);
+            }
+        }
+    }
+    Ok(Value::Dict(entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(input: &[u8]) {
+        let decoded = Value::decode(input).expect("decode should succeed");
+        assert_eq!(decoded.to_bytes(), input);
+    }
+
+    #[test]
+    fn roundtrips_integers() {
+        roundtrip(b"i42e");
+        roundtrip(b"i-42e");
+        roundtrip(b"i0e");
+    }
+
+    #[test]
+    fn roundtrips_byte_strings() {
+        roundtrip(b"4:spam");
+        roundtrip(b"0:");
+    }
+
+    #[test]
+    fn roundtrips_lists() {
+        roundtrip(b"l4:spam4:eggse");
+        roundtrip(b"le");
+    }
+
+    #[test]
+    fn roundtrips_dicts_in_sorted_key_order() {
+        roundtrip(b"d3:cow3:moo4:spam4:eggse");
+    }
+
+    #[test]
+    fn encoder_sorts_keys_regardless_of_insertion_order() {
+        let mut entries = BTreeMap::new();
+        entries.insert(b"zebra".to_vec(), Value::Int(1));
+        entries.insert(b"apple".to_vec(), Value::Int(2));
+        let dict = Value::Dict(entries);
+        assert_eq!(dict.to_bytes(), b"d5:applei2e5:zebrai1ee".to_vec());
+    }
+
+    #[test]
+    fn decode_from_matches_decode_for_nested_values() {
+        let input = b"d3:cow3:moo4:spaml1:a1:bee".to_vec();
+        let in_memory = Value::decode(&input).expect("decode should succeed");
+        let mut reader = input.as_slice();
+        let streamed = Value::decode_from(&mut reader).expect("decode_from should succeed");
+        assert_eq!(in_memory, streamed);
+    }
+
+    #[test]
+    fn decode_from_reports_input_too_short_separately_from_malformed_input() {
+        let mut truncated = b"d3:cow3:moo".as_slice();
+        assert!(matches!(
+            Value::decode_from(&mut truncated),
+            Err(BencodeError::InputTooShort { .. })
+        ));
+
+        let mut malformed = b"d3:cow3:moo3:abc3:defe".as_slice();
+        assert!(matches!(
+            Value::decode_from(&mut malformed),
+            Err(BencodeError::UnsortedDictKey { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_leading_zero_and_negative_zero_integers() {
+        assert!(matches!(
+            Value::decode(b"i01e"),
+            Err(BencodeError::InvalidInteger { .. })
+        ));
+        assert!(matches!(
+            Value::decode(b"i-0e"),
+            Err(BencodeError::InvalidInteger { .. })
+        ));
+    }
+
+    #[test]
+    fn reports_offsets_for_errors() {
+        assert_eq!(
+            Value::decode(b"5:abc"),
+            Err(BencodeError::InputTooShort { offset: 2 })
+        );
+        assert_eq!(
+            Value::decode(b"i5e1:a"),
+            Err(BencodeError::TrailingData { offset: 3 })
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! Converting decoded bencode [`Value`]s into JSON text.
+//!
+//! Bencode byte strings aren't guaranteed to be valid UTF-8, but JSON
+//! strings must be; values (and dict keys) that don't decode as UTF-8 fall
+//! back to [`BytesEncoding`] instead.
+
+use crate::bencode::Value;
+use crate::encoding::{self, BytesEncoding};
+
+pub fn to_json_string(value: &Value, bytes_encoding: BytesEncoding) -> String {
+    let mut out = String::new();
+    write_value(value, bytes_encoding, &mut out);
+    out
+}
+
+fn write_value(value: &Value, bytes_encoding: BytesEncoding, out: &mut String) {
+    match value {
+        Value::Int(n) => out.push_str(&n.to_string()),
+        Value::Bytes(bytes) => write_bytes_as_json_string(bytes, bytes_encoding, out),
+        Value::List(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(item, bytes_encoding, out);
+            }
+            out.push(']');
+        }
+        Value::Dict(entries) => {
+            out.push('{');
+            for (i, (key, value)) in entries.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_bytes_as_json_string(key, bytes_encoding, out);
+                out.push(':');
+                write_value(value, bytes_encoding, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_bytes_as_json_string(bytes: &[u8], bytes_encoding: BytesEncoding, out: &mut String) {
+    out.push('"');
+    match std::str::from_utf8(bytes) {
+        Ok(s) => escape_json_string(s, out),
+        Err(_) => out.push_str(&match bytes_encoding {
+            BytesEncoding::Hex => encoding::hex_encode(bytes),
+            BytesEncoding::Base64 => encoding::base64_encode(bytes),
+        }),
+    }
+    out.push('"');
+}
+
+fn escape_json_string(s: &str, out: &mut String) {
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn renders_integers_and_utf8_strings() {
+        assert_eq!(to_json_string(&Value::Int(42), BytesEncoding::Hex), "42");
+        assert_eq!(
+            to_json_string(&Value::Bytes(b"hello".to_vec()), BytesEncoding::Hex),
+            "\"hello\""
+        );
+    }
+
+    #[test]
+    fn renders_lists_and_dicts() {
+        let list = Value::List(vec![Value::Int(1), Value::Bytes(b"two".to_vec())]);
+        assert_eq!(to_json_string(&list, BytesEncoding::Hex), "[1,\"two\"]");
+
+        let mut entries = BTreeMap::new();
+        entries.insert(b"a".to_vec(), Value::Int(1));
+        let dict = Value::Dict(entries);
+        assert_eq!(to_json_string(&dict, BytesEncoding::Hex), "{\"a\":1}");
+    }
+
+    #[test]
+    fn falls_back_to_the_configured_encoding_for_non_utf8_bytes() {
+        let value = Value::Bytes(vec![0xff, 0x00, 0xff]);
+        assert_eq!(
+            to_json_string(&value, BytesEncoding::Hex),
+            "\"ff00ff\""
+        );
+        assert_eq!(
+            to_json_string(&value, BytesEncoding::Base64),
+            format!("\"{}\"", encoding::base64_encode(&[0xff, 0x00, 0xff]))
+        );
+    }
+
+    #[test]
+    fn escapes_special_characters_in_utf8_strings() {
+        let value = Value::Bytes(b"a\"b\\c\nd".to_vec());
+        assert_eq!(
+            to_json_string(&value, BytesEncoding::Hex),
+            "\"a\\\"b\\\\c\\nd\""
+        );
+    }
+}
@@ -0,0 +1,271 @@
+//! Parsing `.torrent` metainfo files into typed data and deriving their
+//! info-hash.
+
+use crate::bencode::{BencodeError, Value};
+use crate::sha1;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Failure to read or decode a `.torrent` file, or to interpret the decoded
+/// bencode [`Value`] as valid metainfo.
+#[derive(Debug)]
+pub enum MetaInfoError {
+    Io(io::Error),
+    Bencode(BencodeError),
+    MissingField(&'static str),
+    WrongFieldType(&'static str),
+    /// `pieces` was not a concatenation of 20-byte SHA-1 hashes.
+    InvalidPieces,
+}
+
+impl fmt::Display for MetaInfoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "failed to read torrent file: {}", e),
+            Self::Bencode(e) => write!(f, "failed to decode torrent file: {}", e),
+            Self::MissingField(key) => write!(f, "torrent file is missing required field {:?}", key),
+            Self::WrongFieldType(key) => write!(f, "torrent file field {:?} has the wrong type", key),
+            Self::InvalidPieces => {
+                write!(f, "torrent file's pieces field is not a multiple of 20 bytes")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MetaInfoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Bencode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for MetaInfoError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<BencodeError> for MetaInfoError {
+    fn from(e: BencodeError) -> Self {
+        Self::Bencode(e)
+    }
+}
+
+/// A single entry in a multi-file torrent's `info.files` list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileEntry {
+    pub length: u64,
+    pub path: Vec<String>,
+}
+
+/// Whether a torrent describes one file or a directory tree.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mode {
+    SingleFile { length: u64 },
+    MultiFile { files: Vec<FileEntry> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Info {
+    pub name: String,
+    pub piece_length: u64,
+    pub pieces: Vec<[u8; 20]>,
+    pub mode: Mode,
+    /// The `info` dict exactly as decoded, kept around so `info_hash` can
+    /// re-encode the bytes the peer actually agreed on rather than a
+    /// reconstruction that might drop fields this struct doesn't model.
+    raw: Value,
+}
+
+impl Info {
+    fn from_value(value: Value) -> Result<Info, MetaInfoError> {
+        let map = dict(&value, "info")?;
+        let name = get_string(map, "name")?;
+        let piece_length = get_u64(map, "piece length")?;
+        let pieces = parse_pieces(get_bytes(map, "pieces")?)?;
+        let mode = if map.contains_key(b"files".as_slice()) {
+            Mode::MultiFile {
+                files: parse_files(map)?,
+            }
+        } else {
+            Mode::SingleFile {
+                length: get_u64(map, "length")?,
+            }
+        };
+        Ok(Info {
+            name,
+            piece_length,
+            pieces,
+            mode,
+            raw: value,
+        })
+    }
+}
+
+fn parse_pieces(bytes: &[u8]) -> Result<Vec<[u8; 20]>, MetaInfoError> {
+    if !bytes.len().is_multiple_of(20) {
+        return Err(MetaInfoError::InvalidPieces);
+    }
+    Ok(bytes
+        .chunks_exact(20)
+        .map(|chunk| chunk.try_into().expect("chunks_exact(20) yields 20 bytes"))
+        .collect())
+}
+
+fn parse_files(map: &BTreeMap<Vec<u8>, Value>) -> Result<Vec<FileEntry>, MetaInfoError> {
+    let items = match map.get(b"files".as_slice()) {
+        Some(Value::List(items)) => items,
+        Some(_) => return Err(MetaInfoError::WrongFieldType("files")),
+        None => return Err(MetaInfoError::MissingField("files")),
+    };
+    items.iter().map(parse_file_entry).collect()
+}
+
+fn parse_file_entry(value: &Value) -> Result<FileEntry, MetaInfoError> {
+    let map = dict(value, "files[]")?;
+    let length = get_u64(map, "length")?;
+    let path = match map.get(b"path".as_slice()) {
+        Some(Value::List(components)) => components
+            .iter()
+            .map(|c| match c {
+                Value::Bytes(b) => {
+                    String::from_utf8(b.clone()).map_err(|_| MetaInfoError::WrongFieldType("path"))
+                }
+                _ => Err(MetaInfoError::WrongFieldType("path")),
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        Some(_) => return Err(MetaInfoError::WrongFieldType("path")),
+        None => return Err(MetaInfoError::MissingField("path")),
+    };
+    Ok(FileEntry { length, path })
+}
+
+/// A parsed `.torrent` file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetaInfo {
+    pub announce: String,
+    pub info: Info,
+}
+
+impl MetaInfo {
+    /// Read, decode and validate a `.torrent` file from disk.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<MetaInfo, MetaInfoError> {
+        let file = fs::File::open(path)?;
+        let mut reader = io::BufReader::new(file);
+        let value = Value::decode_from(&mut reader)?;
+        MetaInfo::from_value(value)
+    }
+
+    fn from_value(value: Value) -> Result<MetaInfo, MetaInfoError> {
+        let map = dict(&value, "torrent file")?;
+        let announce = get_string(map, "announce")?;
+        let info_value = map
+            .get(b"info".as_slice())
+            .cloned()
+            .ok_or(MetaInfoError::MissingField("info"))?;
+        let info = Info::from_value(info_value)?;
+        Ok(MetaInfo { announce, info })
+    }
+
+    /// The 20-byte SHA-1 hash BitTorrent uses to identify this torrent,
+    /// computed over the canonical bencode re-encoding of the `info` dict.
+    pub fn info_hash(&self) -> [u8; 20] {
+        sha1::digest(&self.info.raw.to_bytes())
+    }
+}
+
+fn dict<'a>(value: &'a Value, what: &'static str) -> Result<&'a BTreeMap<Vec<u8>, Value>, MetaInfoError> {
+    match value {
+        Value::Dict(map) => Ok(map),
+        _ => Err(MetaInfoError::WrongFieldType(what)),
+    }
+}
+
+fn get_bytes<'a>(
+    map: &'a BTreeMap<Vec<u8>, Value>,
+    key: &'static str,
+) -> Result<&'a [u8], MetaInfoError> {
+    match map.get(key.as_bytes()) {
+        Some(Value::Bytes(bytes)) => Ok(bytes),
+        Some(_) => Err(MetaInfoError::WrongFieldType(key)),
+        None => Err(MetaInfoError::MissingField(key)),
+    }
+}
+
+fn get_string(map: &BTreeMap<Vec<u8>, Value>, key: &'static str) -> Result<String, MetaInfoError> {
+    let bytes = get_bytes(map, key)?;
+    String::from_utf8(bytes.to_vec()).map_err(|_| MetaInfoError::WrongFieldType(key))
+}
+
+fn get_u64(map: &BTreeMap<Vec<u8>, Value>, key: &'static str) -> Result<u64, MetaInfoError> {
+    match map.get(key.as_bytes()) {
+        Some(Value::Int(n)) => u64::try_from(*n).map_err(|_| MetaInfoError::WrongFieldType(key)),
+        Some(_) => Err(MetaInfoError::WrongFieldType(key)),
+        None => Err(MetaInfoError::MissingField(key)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_single_file_torrent() -> Vec<u8> {
+        Value::Dict(BTreeMap::from([
+            (
+                b"announce".to_vec(),
+                Value::Bytes(b"http://tracker.example/announce".to_vec()),
+            ),
+            (
+                b"info".to_vec(),
+                Value::Dict(BTreeMap::from([
+                    (b"length".to_vec(), Value::Int(11)),
+                    (b"name".to_vec(), Value::Bytes(b"sample.txt".to_vec())),
+                    (b"piece length".to_vec(), Value::Int(16384)),
+                    (b"pieces".to_vec(), Value::Bytes(vec![0u8; 20])),
+                ])),
+            ),
+        ]))
+        .to_bytes()
+    }
+
+    #[test]
+    fn parses_single_file_torrent() {
+        let bytes = sample_single_file_torrent();
+        let value = Value::decode(&bytes).expect("decode should succeed");
+        let meta = MetaInfo::from_value(value).expect("metainfo should parse");
+        assert_eq!(meta.announce, "http://tracker.example/announce");
+        assert_eq!(meta.info.name, "sample.txt");
+        assert_eq!(meta.info.piece_length, 16384);
+        assert_eq!(meta.info.pieces, vec![[0u8; 20]]);
+        assert_eq!(meta.info.mode, Mode::SingleFile { length: 11 });
+    }
+
+    #[test]
+    fn info_hash_matches_sha1_of_canonical_info_dict() {
+        let bytes = sample_single_file_torrent();
+        let value = Value::decode(&bytes).expect("decode should succeed");
+        let meta = MetaInfo::from_value(value).expect("metainfo should parse");
+        let info_bytes = meta.info.raw.to_bytes();
+        assert_eq!(meta.info_hash(), sha1::digest(&info_bytes));
+    }
+
+    #[test]
+    fn missing_info_dict_is_an_error() {
+        let bytes = Value::Dict(BTreeMap::from([(
+            b"announce".to_vec(),
+            Value::Bytes(b"http://tracker.example/announce".to_vec()),
+        )]))
+        .to_bytes();
+        let value = Value::decode(&bytes).expect("decode should succeed");
+        assert!(matches!(
+            MetaInfo::from_value(value),
+            Err(MetaInfoError::MissingField("info"))
+        ));
+    }
+}